@@ -0,0 +1,25 @@
+//! Traits bridging the LDK event loop to whatever drives it.
+use async_trait::async_trait;
+
+use crate::chan;
+use crate::error;
+use crate::event::Event;
+use crate::json::Value;
+use crate::jsonrpc::Request;
+
+/// Bridges the LDK/command event loop to whatever owns it (the daemon today).
+pub trait Handler {
+    fn emit(&self, event: Event);
+    fn events(&self) -> chan::Receiver<Event>;
+}
+
+/// A chain-of-responsibility extension point for RPC methods the daemon
+/// doesn't implement itself.
+///
+/// `handle` is async so an extension can do network or disk I/O (proxying to
+/// another service, querying a database, ...) without blocking the event
+/// loop that drives it.
+#[async_trait]
+pub trait ExternalHandler: Send + Sync {
+    async fn handle(&self, req: &Request) -> error::Result<Option<Value>>;
+}
@@ -1,7 +1,23 @@
 //! Handler module implementation that
-use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use tokio::sync::RwLock;
+
+mod bolt12;
+mod bump;
+mod channel_policy;
+mod payment_store;
+mod retry;
+mod sweeper;
+
+use bolt12::{Bolt12Manager, OfferFlowState};
+use bump::BumpManager;
+use channel_policy::{ChannelAcceptancePolicy, PolicyDecision};
+use payment_store::{PaymentDirection, PaymentStore};
+use retry::{RetryPolicy, RetryPolicyRequest, RetryTracker};
+use sweeper::OutputSweeper;
+
 use lampo_common::async_trait;
 use lampo_common::bitcoin::Amount;
 use lampo_common::bitcoin::FeeRate;
@@ -31,37 +47,240 @@ pub struct LampoHandler {
     inventory_manager: Arc<LampoInventoryManager>,
     wallet_manager: Arc<dyn WalletManager>,
     chain_manager: Arc<LampoChainManager>,
-    external_handlers: RefCell<Vec<Arc<dyn ExternalHandler>>>,
+    payment_store: PaymentStore,
+    sweeper: OutputSweeper,
+    bump_manager: BumpManager,
+    channel_policy: ChannelAcceptancePolicy,
+    bolt12_manager: Bolt12Manager,
+    retry_tracker: RetryTracker,
+    next_user_channel_id: AtomicU64,
+    external_handlers: RwLock<Vec<Arc<dyn ExternalHandler>>>,
     #[allow(dead_code)]
     emitter: Emitter<Event>,
     subscriber: Subscriber<Event>,
 }
 
-unsafe impl Send for LampoHandler {}
-unsafe impl Sync for LampoHandler {}
-
 impl LampoHandler {
     pub(crate) fn new(lampod: &LampoDaemon) -> Self {
         let emitter = Emitter::default();
         let subscriber = emitter.subscriber();
+        let payment_store = PaymentStore::new(lampod.data_dir());
+        if let Err(err) = payment_store.reload() {
+            log::warn!("unable to reload payment history: {err}");
+        }
+        let sweeper = OutputSweeper::new(lampod.data_dir());
+        if let Err(err) = sweeper.reload() {
+            log::warn!("unable to reload pending spendable outputs: {err}");
+        }
+        let bolt12_manager = Bolt12Manager::new(lampod.data_dir());
+        if let Err(err) = bolt12_manager.reload() {
+            log::warn!("unable to reload outstanding BOLT12 offer state: {err}");
+        }
         Self {
             channel_manager: lampod.channel_manager(),
             peer_manager: lampod.peer_manager(),
             inventory_manager: lampod.inventory_manager(),
             wallet_manager: lampod.wallet_manager(),
             chain_manager: lampod.onchain_manager(),
-            external_handlers: RefCell::new(Vec::new()),
+            payment_store,
+            sweeper,
+            bump_manager: BumpManager::new(),
+            channel_policy: ChannelAcceptancePolicy::new(),
+            bolt12_manager,
+            retry_tracker: RetryTracker::new(RetryPolicy::default()),
+            next_user_channel_id: AtomicU64::new(1),
+            external_handlers: RwLock::new(Vec::new()),
             emitter,
             subscriber,
         }
     }
 
-    pub fn add_external_handler(&self, handler: Arc<dyn ExternalHandler>) -> error::Result<()> {
-        let mut vect = self.external_handlers.borrow_mut();
+    pub async fn add_external_handler(&self, handler: Arc<dyn ExternalHandler>) -> error::Result<()> {
+        let mut vect = self.external_handlers.write().await;
         vect.push(handler);
         Ok(())
     }
 
+    /// Replace the policy used to decide whether to accept inbound channel
+    /// open requests. Surfaced through an external command so RPC callers
+    /// can configure allow/deny lists, a minimum funding threshold, and
+    /// whether anchor-output channels and zero-conf are accepted.
+    pub fn configure_channel_policy(&self, config: channel_policy::PolicyConfig) {
+        self.channel_policy.configure(config);
+    }
+
+    /// Replace the advisory budget used to decide when a payment's path
+    /// failures are worth a warning log. This only tunes when
+    /// `record_path_failure` reports a payment as over budget; it never
+    /// triggers a retry or resend itself — LDK keeps retrying a payment
+    /// internally according to the `Retry` policy it was sent with, and
+    /// only the terminal `PaymentFailed` event marks it failed. Surfaced
+    /// through an external command so RPC callers can tune
+    /// `max_attempts`/`total_timeout` without a restart.
+    pub fn configure_retry_policy(&self, policy: RetryPolicy) {
+        self.retry_tracker.configure(policy);
+    }
+
+    // The channel manager already implements `NodeIdLookUp` itself, so
+    // resolving the real node id behind a blinded hop when routing BOLT12
+    // onion messages (invoice requests/invoices) just means handing
+    // `self.channel_manager.manager()` to whatever constructs the onion
+    // messenger -- there is no separate lookup type to build here.
+
+    /// Build a static BOLT12 `Offer`, advertising a blinded path through our
+    /// usable channels so the offer doesn't reveal our node id.
+    pub fn create_offer(&self, amount_msat: Option<u64>, description: String) -> error::Result<bolt12::StaticOffer> {
+        let channels = self.channel_manager.manager().list_usable_channels();
+        let route_info: Vec<bolt12::ChannelRouteInfo> = channels.iter().map(Into::into).collect();
+        let forward_nodes = bolt12::blinded_forward_nodes(&route_info);
+        if forward_nodes.is_empty() {
+            log::warn!(target: "bolt12", "no usable channel to route a blinded reply path through; the offer will reveal our node id");
+        }
+        let mut builder = self
+            .channel_manager
+            .manager()
+            .create_offer_builder(forward_nodes, description)
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        if let Some(amount_msat) = amount_msat {
+            builder = builder.amount_msats(amount_msat);
+        }
+        builder
+            .build()
+            .map_err(|err| error::anyhow!("unable to build offer: {:?}", err))
+    }
+
+    /// Pay a BOLT12 offer: send an `invoice_request` over the offer's
+    /// blinded reply path, then pay the `Bolt12Invoice` once it comes back.
+    pub fn pay_offer(&self, offer: &bolt12::StaticOffer, amount_msat: Option<u64>) -> error::Result<()> {
+        let payment_id = lampo_common::ldk::ln::channelmanager::PaymentId(
+            self.channel_manager.keys_manager().get_secure_random_bytes(),
+        );
+        self.bolt12_manager.track(payment_id, OfferFlowState::AwaitingInvoiceRequest);
+        let result = self
+            .channel_manager
+            .manager()
+            .pay_for_offer(
+                offer,
+                None,
+                amount_msat,
+                None,
+                payment_id,
+                lampo_common::ldk::ln::channelmanager::Retry::Attempts(3),
+                None,
+            )
+            .map_err(|err| error::anyhow!("unable to pay offer: {:?}", err));
+        if result.is_ok() {
+            self.bolt12_manager.advance(payment_id, OfferFlowState::AwaitingInvoice);
+        } else {
+            self.bolt12_manager.advance(payment_id, OfferFlowState::Failed);
+        }
+        result
+    }
+
+    /// Check every sweep still awaiting confirmation: drop the ones the
+    /// chain manager now sees confirmed, and rebroadcast the rest at a
+    /// strictly higher feerate so the replacement can relay/replace the
+    /// prior attempt. A batch that was never broadcast goes out at the
+    /// estimated feerate.
+    async fn poll_sweeps(&self) -> error::Result<()> {
+        for view in self.sweeper.snapshot() {
+            let Some(txid) = view.sweep_txid else {
+                // Never broadcast, most likely because the process was
+                // killed between `track()` and `record_broadcast()`. Fall
+                // through to the same broadcast path used for a bump below,
+                // at the initial estimated feerate, instead of leaving this
+                // batch stuck forever.
+                let estimated_fee_rate = self
+                    .chain_manager
+                    .backend
+                    .fee_rate_estimation(6)
+                    .await
+                    .map_err(|err| error::anyhow!("Sweep Fee Estimation Error: {err}"))? as u32;
+                log::info!("broadcasting never-sent sweep batch `{}` at {estimated_fee_rate} sat/vB", view.id);
+                self.broadcast_sweep(view.id, &view.descriptors, estimated_fee_rate).await?;
+                continue;
+            };
+            let confirmations = self
+                .chain_manager
+                .backend
+                .get_transaction_confirmations(&txid)
+                .await
+                .unwrap_or(0);
+            if confirmations > 0 {
+                log::info!("sweep `{txid}` confirmed with {confirmations} confirmation(s)");
+                self.sweeper.mark_confirmed(view.id);
+                continue;
+            }
+            let estimated_fee_rate = self
+                .chain_manager
+                .backend
+                .fee_rate_estimation(6)
+                .await
+                .map_err(|err| error::anyhow!("Sweep Fee Estimation Error: {err}"))? as u32;
+            let bumped_fee_rate = match view.last_fee_rate_sat_per_vb {
+                Some(previous) => previous.max(estimated_fee_rate) + 1,
+                None => estimated_fee_rate,
+            };
+            log::info!(
+                "rebroadcasting unconfirmed sweep `{txid}` at {bumped_fee_rate} sat/vB (was {:?})",
+                view.last_fee_rate_sat_per_vb
+            );
+            self.broadcast_sweep(view.id, &view.descriptors, bumped_fee_rate).await?;
+        }
+        Ok(())
+    }
+
+    /// Drive [`Self::poll_sweeps`] on a timer so a sweep left over from a
+    /// previous run (or one whose confirmation is still pending) gets
+    /// serviced even if no new `SpendableOutputs` event ever arrives.
+    ///
+    /// `LampoHandler::new` cannot spawn this itself since it runs before the
+    /// handler is wrapped in the `Arc` this needs to outlive the spawned
+    /// task; whatever constructs the `Arc<LampoHandler>` (the daemon
+    /// startup path) must call this once after that point.
+    pub fn spawn_sweep_loop(self: &Arc<Self>) {
+        let handler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                if let Err(err) = handler.poll_sweeps().await {
+                    log::warn!("sweep poll failed: {err}");
+                }
+            }
+        });
+    }
+
+    async fn broadcast_sweep(
+        &self,
+        id: u64,
+        descriptors: &[lampo_common::ldk::sign::SpendableOutputDescriptor],
+        fee_rate_sat_per_vb: u32,
+    ) -> error::Result<()> {
+        let change_destination = self.wallet_manager.get_onchain_address()?;
+        let transaction = self
+            .channel_manager
+            .keys_manager()
+            .spend_spendable_outputs(
+                &descriptors.iter().collect::<Vec<_>>(),
+                vec![],
+                change_destination.script_pubkey(),
+                FeeRate::from_sat_per_vb_unchecked(fee_rate_sat_per_vb as u64),
+                None,
+                &lampo_common::bitcoin::secp256k1::Secp256k1::new(),
+            )
+            .map_err(|_| error::anyhow!("unable to build sweeping transaction"))?;
+        let txid = transaction.compute_txid();
+        self.chain_manager.backend.broadcast_transaction(&transaction).await?;
+        self.sweeper.record_broadcast(id, txid, fee_rate_sat_per_vb);
+        log::info!("broadcast sweep transaction `{txid}` at {fee_rate_sat_per_vb} sat/vB");
+        self.emit(Event::Lightning(LightningEvent::OnChainEvent {
+            txid: txid.to_string(),
+            message: "swept spendable outputs from a closed channel".to_owned(),
+        }));
+        Ok(())
+    }
+
     /// Call any method supported by the lampod configuration. This includes
     /// a lot of handler code. This function serves as a broker pattern in some ways,
     /// but it may also function as a chain of responsibility pattern in certain cases.
@@ -94,13 +313,32 @@ impl EventHandler for LampoHandler {
 #[async_trait]
 impl Handler for LampoHandler {
     async fn react(&self, event: crate::command::Command) -> error::Result<json::Value> {
-        let handler = self.external_handlers.borrow();
         match event {
             Command::ExternalCommand(req) => {
-                log::debug!(target: "lampo", "external handler size {}", handler.len());
-                for handler in handler.iter() {
-                    // FIXME: this is blocking the async execution!!
-                    if let Some(resp) = handler.handle(&req)? {
+                if req.method == "list_payments" {
+                    let payments = self
+                        .payment_store
+                        .list()
+                        .iter()
+                        .map(payment_store::PaymentDetails::as_response)
+                        .collect::<Vec<_>>();
+                    return Ok(json::to_value(payments)?);
+                }
+                if req.method == "configure_channel_policy" {
+                    let request: channel_policy::PolicyConfigRequest = json::from_value(req.params.clone())?;
+                    let config = channel_policy::PolicyConfig::try_from(request)?;
+                    self.configure_channel_policy(config);
+                    return Ok(json::Value::Null);
+                }
+                if req.method == "configure_retry_policy" {
+                    let request: RetryPolicyRequest = json::from_value(req.params.clone())?;
+                    self.configure_retry_policy(RetryPolicy::from(request));
+                    return Ok(json::Value::Null);
+                }
+                let handlers = self.external_handlers.read().await.clone();
+                log::debug!(target: "lampo", "external handler size {}", handlers.len());
+                for handler in handlers.iter() {
+                    if let Some(resp) = handler.handle(&req).await? {
                         return Ok(resp);
                     }
                 }
@@ -121,7 +359,62 @@ impl Handler for LampoHandler {
                 is_announced: _,
                 params: _
             } => {
-                Err(error::anyhow!("Request for open a channel received, unfortunatly we do not support this feature yet."))
+                let node_id = lampo_common::ldk::routing::gossip::NodeId::from_pubkey(&counterparty_node_id);
+                let wants_anchors = channel_type.supports_anchors_zero_fee_htlc_tx();
+                let decision = self
+                    .channel_policy
+                    .evaluate(&node_id, funding_satoshis, wants_anchors);
+                // Unique per accepted request so downstream `ChannelPending`/
+                // `ChannelReady` events can be correlated back to it even
+                // when several inbound opens are in flight concurrently.
+                let user_channel_id = self.next_user_channel_id.fetch_add(1, Ordering::Relaxed) as u128;
+                match decision {
+                    PolicyDecision::Accept { zero_conf: true } => {
+                        self.channel_manager
+                            .manager()
+                            .accept_inbound_channel_from_trusted_peer_0conf(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                user_channel_id,
+                                None,
+                            )
+                            .map_err(|err| error::anyhow!("{:?}", err))?;
+                        log::info!("accepted zero-conf channel `{user_channel_id}` from `{counterparty_node_id}`");
+                        self.emit(Event::Lightning(LightningEvent::ChannelEvent {
+                            state: "accepted".to_owned(),
+                            message: format!("accepted zero-conf channel {user_channel_id} from {counterparty_node_id}"),
+                        }));
+                        Ok(())
+                    }
+                    PolicyDecision::Accept { zero_conf: false } => {
+                        self.channel_manager
+                            .manager()
+                            .accept_inbound_channel(&temporary_channel_id, &counterparty_node_id, user_channel_id)
+                            .map_err(|err| error::anyhow!("{:?}", err))?;
+                        log::info!("accepted channel `{user_channel_id}` from `{counterparty_node_id}`");
+                        self.emit(Event::Lightning(LightningEvent::ChannelEvent {
+                            state: "accepted".to_owned(),
+                            message: format!("accepted channel {user_channel_id} from {counterparty_node_id}"),
+                        }));
+                        Ok(())
+                    }
+                    PolicyDecision::Reject { reason } => {
+                        self.channel_manager
+                            .manager()
+                            .force_close_without_broadcasting_txn(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                reason.clone(),
+                            )
+                            .map_err(|err| error::anyhow!("{:?}", err))?;
+                        log::warn!("rejected channel from `{counterparty_node_id}`: {reason}");
+                        self.emit(Event::Lightning(LightningEvent::ChannelEvent {
+                            state: "rejected".to_owned(),
+                            message: format!("rejected channel from {counterparty_node_id}: {reason}"),
+                        }));
+                        Ok(())
+                    }
+                }
             }
             ldk::events::Event::ChannelReady {
                 channel_id,
@@ -239,6 +532,16 @@ impl Handler for LampoHandler {
                     ldk::events::PaymentPurpose::Bolt12RefundPayment { payment_preimage, .. } => payment_preimage,
                     ldk::events::PaymentPurpose::SpontaneousPayment(preimage) => Some(preimage),
                 };
+                self.payment_store.upsert_pending(
+                    payment_hash,
+                    PaymentDirection::Inbound,
+                    Some(amount_msat),
+                );
+                self.emit(Event::Lightning(LightningEvent::PaymentEvent {
+                    state: PaymentState::Pending,
+                    payment_hash: Some(payment_hash.to_string()),
+                    path: vec![],
+                }));
                 self.channel_manager
                     .manager()
                     .claim_funds(preimage.unwrap());
@@ -261,12 +564,33 @@ impl Handler for LampoHandler {
                     ldk::events::PaymentPurpose::Bolt12RefundPayment { payment_preimage, payment_secret, .. } => (payment_preimage, Some(payment_secret)),
                     ldk::events::PaymentPurpose::SpontaneousPayment(preimage) => (Some(preimage), None),
                 };
-                log::warn!("please note the payments are not make persistent for the moment");
-                // FIXME: make peristent these information
+                self.payment_store
+                    .mark_claimed(payment_hash, payment_preimage, payment_secret);
+                self.emit(Event::Lightning(LightningEvent::PaymentEvent {
+                    state: PaymentState::Success,
+                    payment_hash: Some(payment_hash.to_string()),
+                    path: vec![],
+                }));
+                log::info!("payment `{payment_hash}` claimed for `{amount_msat}` msat");
                 Ok(())
             }
-            ldk::events::Event::PaymentSent { .. } => {
-                log::info!("payment sent: `{:?}`", event);
+            ldk::events::Event::PaymentSent {
+                payment_id,
+                payment_hash,
+                payment_preimage,
+                ..
+            } => {
+                if let Some(payment_id) = payment_id {
+                    self.bolt12_manager.finish(&payment_id);
+                }
+                self.payment_store
+                    .mark_sent(payment_hash, Some(payment_preimage));
+                self.emit(Event::Lightning(LightningEvent::PaymentEvent {
+                    state: PaymentState::Success,
+                    payment_hash: Some(payment_hash.to_string()),
+                    path: vec![],
+                }));
+                log::info!("payment `{payment_hash}` sent, preimage `{payment_preimage}`");
                 Ok(())
             },
             ldk::events::Event::PaymentPathSuccessful { payment_hash, path, .. } => {
@@ -275,6 +599,169 @@ impl Handler for LampoHandler {
                 self.emit(Event::Lightning(hop));
                 Ok(())
             },
+            ldk::events::Event::PaymentFailed {
+                payment_id,
+                payment_hash,
+                reason,
+                ..
+            } => {
+                self.bolt12_manager.finish(&payment_id);
+                if let Some(payment_hash) = payment_hash {
+                    self.payment_store.mark_failed(payment_hash);
+                    self.retry_tracker.clear(&payment_hash);
+                }
+                log::warn!("payment `{:?}` failed: `{:?}`", payment_hash, reason);
+                self.emit(Event::Lightning(LightningEvent::PaymentEvent {
+                    state: PaymentState::Failure,
+                    payment_hash: payment_hash.map(|hash| hash.to_string()),
+                    path: vec![],
+                }));
+                Ok(())
+            }
+            ldk::events::Event::PaymentPathFailed {
+                payment_hash,
+                payment_failed_permanently,
+                path,
+                short_channel_id,
+                failure,
+                ..
+            } => {
+                let failing_hop = short_channel_id
+                    .and_then(|scid| path.hops.iter().position(|hop| hop.short_channel_id == scid));
+                log::warn!(
+                    "payment `{payment_hash}` path failed at hop `{:?}` (scid `{:?}`): `{:?}`",
+                    failing_hop,
+                    short_channel_id,
+                    failure
+                );
+                // A path failure is never terminal on its own: LDK keeps
+                // retrying internally under the `Retry` policy the payment
+                // was sent with, and a single MPP "round" can fail several
+                // paths at once without the overall payment failing. Only
+                // the terminal `PaymentFailed` event marks the payment
+                // Failed and emits a `Failure` state; this arm just tracks
+                // the budget so we can log once a payment is clearly
+                // overrunning it.
+                if self.retry_tracker.record_path_failure(payment_hash) {
+                    log::warn!(
+                        "payment `{payment_hash}` has exceeded its configured retry budget; still awaiting LDK's terminal event"
+                    );
+                }
+                if payment_failed_permanently {
+                    log::warn!("payment `{payment_hash}` path failed permanently; awaiting terminal `PaymentFailed` event");
+                }
+                Ok(())
+            }
+            ldk::events::Event::BumpTransaction(bump_event) => {
+                let (claim_id, commitment_txid) = match &bump_event {
+                    ldk::events::bump_transaction::BumpTransactionEvent::ChannelClose {
+                        claim_id,
+                        commitment_tx,
+                        ..
+                    } => (*claim_id, commitment_tx.compute_txid()),
+                    ldk::events::bump_transaction::BumpTransactionEvent::HTLCResolution {
+                        claim_id,
+                        ..
+                    } => (*claim_id, lampo_common::bitcoin::Txid::all_zeros()),
+                };
+                log::info!("bump requested for claim `{:?}` (parent `{commitment_txid}`)", claim_id);
+                let estimated_fee_rate = self
+                    .chain_manager
+                    .backend
+                    .fee_rate_estimation(1)
+                    .await
+                    .map_err(|err| error::anyhow!("Bump Fee Estimation Error: {err}"))? as u32;
+                let fee_rate = self.bump_manager.next_fee_rate(claim_id, estimated_fee_rate);
+                let candidates = self
+                    .wallet_manager
+                    .list_confirmed_utxos()
+                    .map_err(|err| error::anyhow!("unable to list confirmed utxos: {err}"))?;
+                let reserved = self.bump_manager.reserve_available(claim_id, &candidates)?;
+                log::debug!("reserved {} UTXO(s) for claim `{:?}`", reserved.len(), claim_id);
+                // Raise LDK's target feerate to the one derived above (1 sat/vB
+                // is 250 sat/kW), never lowering it.
+                let mut bump_event = bump_event;
+                match &mut bump_event {
+                    ldk::events::bump_transaction::BumpTransactionEvent::ChannelClose {
+                        package_target_feerate_sat_per_1000_weight: target,
+                        ..
+                    }
+                    | ldk::events::bump_transaction::BumpTransactionEvent::HTLCResolution {
+                        target_feerate_sat_per_1000_weight: target,
+                        ..
+                    } => *target = (*target).max(fee_rate.saturating_mul(250)),
+                }
+                // `handle_event` is where LDK's own coin source actually
+                // selects and signs inputs, entirely independent of
+                // `reserved` above; serializing this window is what stops a
+                // second concurrent `BumpTransaction` event from having that
+                // coin source pick the same wallet UTXO while this claim's
+                // selection is still in flight.
+                let result = self.bump_manager.with_exclusive_selection(|| {
+                    self.channel_manager
+                        .bump_transaction_event_handler()
+                        .handle_event(&bump_event)
+                });
+                // Release regardless of outcome: a further bump for this
+                // claim re-reserves on its own next `BumpTransaction` event,
+                // and a failed attempt must not sequester coins forever.
+                self.bump_manager.release(claim_id);
+                if let Err(err) = result {
+                    error::bail!("unable to build CPFP bump for claim `{:?}`: {:?}", claim_id, err);
+                }
+                self.emit(Event::Lightning(LightningEvent::OnChainEvent {
+                    txid: commitment_txid.to_string(),
+                    message: format!("submitted CPFP bump at no less than {fee_rate} sat/vB for claim {:?}", claim_id),
+                }));
+                Ok(())
+            }
+            ldk::events::Event::SpendableOutputs {
+                outputs,
+                channel_id,
+            } => {
+                log::info!(
+                    "received {} spendable output(s) from channel `{:?}`",
+                    outputs.len(),
+                    channel_id
+                );
+                // Service any sweep still awaiting confirmation before
+                // taking on the new batch, so a restart between a prior
+                // `SpendableOutputs` event and its confirmation still makes
+                // forward progress.
+                if let Err(err) = self.poll_sweeps().await {
+                    log::warn!("error polling outstanding sweeps: {err}");
+                }
+                let id = self.sweeper.track(outputs.clone());
+                let fee_rate = self
+                    .chain_manager
+                    .backend
+                    .fee_rate_estimation(6)
+                    .await
+                    .map_err(|err| error::anyhow!("Sweep Fee Estimation Error: {err}"))?;
+                self.broadcast_sweep(id, &outputs, fee_rate as u32).await?;
+                Ok(())
+            }
+            // Building or sending the `invoice_request` for a BOLT12 offer
+            // we're paying failed outright (e.g. no route to the offer's
+            // blinded path). There is no separate "invoice request
+            // received"/"invoice received" event to handle here: once an
+            // `invoice_request` reaches us, the channel manager answers it
+            // with a signed `Bolt12Invoice` over the onion messenger
+            // automatically, and once an invoice comes back for a payment
+            // we sent, it's paid automatically too -- both already surface
+            // through the `PaymentClaimable`/`PaymentSent`/`PaymentFailed`
+            // arms above.
+            ldk::events::Event::InvoiceRequestFailed { payment_id } => {
+                log::warn!("invoice_request for payment `{:?}` failed to build or send", payment_id);
+                self.bolt12_manager.advance(payment_id, OfferFlowState::Failed);
+                self.bolt12_manager.finish(&payment_id);
+                self.emit(Event::Lightning(LightningEvent::PaymentEvent {
+                    state: PaymentState::Failure,
+                    payment_hash: None,
+                    path: vec![],
+                }));
+                Ok(())
+            }
             _ => Err(error::anyhow!("unexpected ldk event: {:?}", event)),
         }
     }
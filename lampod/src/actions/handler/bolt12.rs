@@ -0,0 +1,245 @@
+//! BOLT12 offer creation, receive and pay flows.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use lampo_common::error;
+use lampo_common::ldk::ln::channelmanager::PaymentId;
+use lampo_common::ldk::offers::offer::Offer;
+use lampo_common::ldk::routing::gossip::NodeId;
+use lampo_common::ldk::blinded_path::message::ForwardNode;
+
+/// State kept for an offer/invoice-request round-trip so it can be resumed
+/// if the daemon restarts mid-flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum OfferFlowState {
+    /// We are paying an offer and the `invoice_request` for it is being
+    /// built and sent.
+    AwaitingInvoiceRequest,
+    /// We sent an `invoice_request` and are waiting for the `Bolt12Invoice`.
+    AwaitingInvoice,
+    /// The invoice came back and we are paying it.
+    Paying,
+    /// Building or sending the `invoice_request` failed outright.
+    Failed,
+}
+
+/// Wire format for a tracked flow, persisted through the same
+/// temp-file-then-rename pattern as [`super::payment_store::PaymentStore`]
+/// and [`super::sweeper::OutputSweeper`]. The `PaymentId` is hex-encoded
+/// since it doesn't implement serde.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PersistedFlow {
+    payment_id: String,
+    state: OfferFlowState,
+}
+
+fn encode_hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(hex_str: &str) -> error::Result<[u8; 32]> {
+    if hex_str.len() != 64 {
+        error::bail!("expected a 32-byte hex string, got {} chars", hex_str.len());
+    }
+    let mut out = [0u8; 32];
+    for (i, chunk) in out.iter_mut().enumerate() {
+        *chunk = u8::from_str_radix(&hex_str[i * 2..i * 2 + 2], 16)
+            .map_err(|err| error::anyhow!("invalid hex in bolt12 flow store: {err}"))?;
+    }
+    Ok(out)
+}
+
+/// Tracks in-flight BOLT12 offers and invoice requests across a restart.
+///
+/// Mutations are kept in an in-memory map that mirrors a JSON file at
+/// `<data_dir>/bolt12_flows.json`; every mutation rewrites that file so an
+/// in-flight offer/invoice-request survives a restart, and
+/// [`Bolt12Manager::reload`] restores the in-memory view from it on
+/// startup.
+pub struct Bolt12Manager {
+    flows: Mutex<HashMap<PaymentId, OfferFlowState>>,
+    storage_path: PathBuf,
+}
+
+impl Bolt12Manager {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            flows: Mutex::new(HashMap::new()),
+            storage_path: data_dir.join("bolt12_flows.json"),
+        }
+    }
+
+    /// Restore flows that were persisted before a previous shutdown.
+    pub fn reload(&self) -> error::Result<()> {
+        if !self.storage_path.exists() {
+            log::info!(target: "bolt12", "no pending offer flows found at `{}`", self.storage_path.display());
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.storage_path)
+            .map_err(|err| error::anyhow!("unable to read `{}`: {err}", self.storage_path.display()))?;
+        let persisted: Vec<PersistedFlow> = lampo_common::json::from_str(&raw)
+            .map_err(|err| error::anyhow!("unable to parse `{}`: {err}", self.storage_path.display()))?;
+        let mut flows = self.flows.lock().unwrap();
+        for entry in persisted {
+            let payment_id = PaymentId(decode_hex(&entry.payment_id)?);
+            flows.insert(payment_id, entry.state);
+        }
+        log::info!(target: "bolt12", "reloaded {} pending offer flow(s) from `{}`", flows.len(), self.storage_path.display());
+        Ok(())
+    }
+
+    pub fn track(&self, payment_id: PaymentId, state: OfferFlowState) {
+        let mut flows = self.flows.lock().unwrap();
+        flows.insert(payment_id, state);
+        self.persist(&flows);
+    }
+
+    pub fn advance(&self, payment_id: PaymentId, state: OfferFlowState) {
+        let mut flows = self.flows.lock().unwrap();
+        flows.insert(payment_id, state);
+        self.persist(&flows);
+    }
+
+    pub fn finish(&self, payment_id: &PaymentId) {
+        let mut flows = self.flows.lock().unwrap();
+        flows.remove(payment_id);
+        self.persist(&flows);
+    }
+
+    fn persist(&self, flows: &HashMap<PaymentId, OfferFlowState>) {
+        let persisted: Vec<PersistedFlow> = flows
+            .iter()
+            .map(|(payment_id, state)| PersistedFlow {
+                payment_id: encode_hex(&payment_id.0),
+                state: *state,
+            })
+            .collect();
+        let json = match lampo_common::json::to_string_pretty(&persisted) {
+            Ok(json) => json,
+            Err(err) => {
+                log::warn!(target: "bolt12", "unable to serialize pending offer flows: {err}");
+                return;
+            }
+        };
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        if let Err(err) = fs::write(&tmp_path, json) {
+            log::warn!(target: "bolt12", "unable to write `{}`: {err}", tmp_path.display());
+            return;
+        }
+        if let Err(err) = fs::rename(&tmp_path, &self.storage_path) {
+            log::warn!(target: "bolt12", "unable to persist pending offer flows to `{}`: {err}", self.storage_path.display());
+        }
+    }
+}
+
+/// The subset of `ChannelDetails` that [`blinded_forward_nodes`] actually
+/// needs. Kept separate from LDK's full `ChannelDetails` so the selection
+/// logic can be unit tested without constructing that (much larger,
+/// `Default`-less) type.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelRouteInfo {
+    pub counterparty_node_id: NodeId,
+    pub short_channel_id: Option<u64>,
+    pub is_usable: bool,
+}
+
+impl From<&lampo_common::ldk::ln::channelmanager::ChannelDetails> for ChannelRouteInfo {
+    fn from(channel: &lampo_common::ldk::ln::channelmanager::ChannelDetails) -> Self {
+        Self {
+            counterparty_node_id: NodeId::from_pubkey(&channel.counterparty.node_id),
+            short_channel_id: channel.short_channel_id,
+            is_usable: channel.is_usable,
+        }
+    }
+}
+
+/// Build the list of intermediate hops used to blind the reply path back to
+/// us, so the offer we hand out doesn't reveal our node id.
+///
+/// Only channels that are currently usable (the peer is connected and the
+/// channel isn't pending/closing) and that have an assigned `short_channel_id`
+/// can serve as a blinded hop, since the onion messenger needs a real scid to
+/// route the reply through.
+pub fn blinded_forward_nodes(channels: &[ChannelRouteInfo]) -> Vec<ForwardNode> {
+    channels
+        .iter()
+        .filter(|channel| channel.is_usable)
+        .filter_map(|channel| {
+            channel.short_channel_id.map(|short_channel_id| ForwardNode {
+                node_id: channel.counterparty_node_id,
+                short_channel_id,
+            })
+        })
+        .collect()
+}
+
+/// A static `Offer` we advertise to be paid.
+pub type StaticOffer = Offer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_id(byte: u8) -> NodeId {
+        let hex_str: String = std::iter::once("02".to_owned())
+            .chain(std::iter::repeat(format!("{byte:02x}")).take(32))
+            .collect();
+        let bytes: Vec<u8> = (0..hex_str.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex_str[i..i + 2], 16).unwrap())
+            .collect();
+        NodeId::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn includes_usable_channels_with_a_scid() {
+        let channels = vec![ChannelRouteInfo {
+            counterparty_node_id: node_id(1),
+            short_channel_id: Some(42),
+            is_usable: true,
+        }];
+        let nodes = blinded_forward_nodes(&channels);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].short_channel_id, 42);
+        assert_eq!(nodes[0].node_id, node_id(1));
+    }
+
+    #[test]
+    fn excludes_unusable_or_scid_less_channels() {
+        let channels = vec![
+            ChannelRouteInfo {
+                counterparty_node_id: node_id(2),
+                short_channel_id: Some(7),
+                is_usable: false,
+            },
+            ChannelRouteInfo {
+                counterparty_node_id: node_id(3),
+                short_channel_id: None,
+                is_usable: true,
+            },
+        ];
+        assert!(blinded_forward_nodes(&channels).is_empty());
+    }
+
+    #[test]
+    fn reload_restores_persisted_flows() {
+        let data_dir = std::env::temp_dir().join(format!("lampo-bolt12-test-{}", std::process::id()));
+        fs::create_dir_all(&data_dir).unwrap();
+        let payment_id = PaymentId([5u8; 32]);
+        {
+            let manager = Bolt12Manager::new(data_dir.clone());
+            manager.track(payment_id, OfferFlowState::AwaitingInvoice);
+        }
+
+        let reloaded = Bolt12Manager::new(data_dir.clone());
+        reloaded.reload().unwrap();
+        assert_eq!(
+            reloaded.flows.lock().unwrap().get(&payment_id).copied(),
+            Some(OfferFlowState::AwaitingInvoice)
+        );
+
+        let _ = fs::remove_dir_all(data_dir);
+    }
+}
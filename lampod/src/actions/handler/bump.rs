@@ -0,0 +1,155 @@
+//! CPFP fee bumping for anchor-output channels.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lampo_common::bitcoin::OutPoint;
+use lampo_common::error;
+
+/// Upper bound on how many wallet UTXOs a single CPFP bump is allowed to
+/// claim, so one bump can't starve every other concurrent bump of coins.
+const MAX_INPUTS_PER_BUMP: usize = 4;
+
+/// Tracks, per in-flight claim, which wallet UTXOs are reserved for its CPFP
+/// bump and the feerate last derived for it.
+///
+/// Reservation covers at most [`MAX_INPUTS_PER_BUMP`] coins per claim rather
+/// than the whole confirmed UTXO set, so two bumps that draw from disjoint
+/// coins can both proceed; only a coin another in-flight claim has already
+/// taken is refused.
+pub struct BumpManager {
+    reserved: Mutex<HashMap<OutPoint, ClaimId>>,
+    last_fee_rate: Mutex<HashMap<ClaimId, u32>>,
+    /// Serializes the window around `handle_event`'s own coin selection.
+    /// `reserved` only tracks coins *this* subsystem handed out; LDK's real
+    /// `CoinSelectionSource` knows nothing about it and would happily pick
+    /// the same wallet UTXO for two concurrent `BumpTransaction` events.
+    /// Holding this lock for the duration of `handle_event` is what actually
+    /// keeps two such events from racing each other into double-spending a
+    /// coin.
+    selection_lock: Mutex<()>,
+}
+
+/// Re-exported so callers don't need to know the LDK module path.
+pub type ClaimId = lampo_common::ldk::events::bump_transaction::ClaimId;
+
+impl BumpManager {
+    pub fn new() -> Self {
+        Self {
+            reserved: Mutex::new(HashMap::new()),
+            last_fee_rate: Mutex::new(HashMap::new()),
+            selection_lock: Mutex::new(()),
+        }
+    }
+
+    /// Run `handle_event` (or whatever else performs the real coin
+    /// selection/signing for a bump) with exclusive access, so no other
+    /// claim's bump can select a wallet UTXO at the same time. This is the
+    /// only thing that actually prevents two concurrent `BumpTransaction`
+    /// events from racing into LDK's coin source and picking the same coin;
+    /// `reserved` alone is bookkeeping this crate keeps, not something the
+    /// coin source consults.
+    pub fn with_exclusive_selection<T>(&self, handle: impl FnOnce() -> T) -> T {
+        let _guard = self.selection_lock.lock().unwrap();
+        handle()
+    }
+
+    /// Claim up to [`MAX_INPUTS_PER_BUMP`] UTXOs from `candidates` that
+    /// aren't already reserved by a *different* in-flight claim. Coins
+    /// already reserved by this same `claim_id` (a retry/rebump) are kept.
+    pub fn reserve_available(
+        &self,
+        claim_id: ClaimId,
+        candidates: &[OutPoint],
+    ) -> error::Result<Vec<OutPoint>> {
+        let mut reserved = self.reserved.lock().unwrap();
+        let free: Vec<OutPoint> = candidates
+            .iter()
+            .filter(|utxo| reserved.get(utxo).map_or(true, |owner| *owner == claim_id))
+            .take(MAX_INPUTS_PER_BUMP)
+            .copied()
+            .collect();
+        if free.is_empty() {
+            error::bail!(
+                "no free UTXOs available to fund CPFP bump for claim `{:?}` (all candidates are reserved by other in-flight bumps)",
+                claim_id
+            );
+        }
+        for utxo in &free {
+            reserved.insert(*utxo, claim_id);
+        }
+        Ok(free)
+    }
+
+    /// Release every UTXO reserved for `claim_id`. The handler calls this as
+    /// soon as `handle_event` returns for the claim, whatever its outcome.
+    pub fn release(&self, claim_id: ClaimId) {
+        self.reserved.lock().unwrap().retain(|_, owner| *owner != claim_id);
+    }
+
+    /// Feerate (sat/vB) to use for the next bump of `claim_id`: at least
+    /// `estimated`, but strictly higher than whatever this claim was last
+    /// broadcast at, so a repeat bump always qualifies as an RBF/CPFP
+    /// replacement instead of being rejected as a no-op or lower-fee retry.
+    pub fn next_fee_rate(&self, claim_id: ClaimId, estimated: u32) -> u32 {
+        let mut last_fee_rate = self.last_fee_rate.lock().unwrap();
+        let next = match last_fee_rate.get(&claim_id) {
+            Some(previous) => estimated.max(previous + 1),
+            None => estimated,
+        };
+        last_fee_rate.insert(claim_id, next);
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lampo_common::bitcoin::Txid;
+
+    fn utxo(vout: u32) -> OutPoint {
+        OutPoint {
+            txid: Txid::all_zeros(),
+            vout,
+        }
+    }
+
+    #[test]
+    fn disjoint_claims_can_reserve_concurrently() {
+        let manager = BumpManager::new();
+        let claim_a = ClaimId([1u8; 32]);
+        let claim_b = ClaimId([2u8; 32]);
+        let reserved_a = manager.reserve_available(claim_a, &[utxo(0)]).unwrap();
+        assert_eq!(reserved_a, vec![utxo(0)]);
+        // A different claim drawing from a disjoint coin must still succeed.
+        let reserved_b = manager.reserve_available(claim_b, &[utxo(1)]).unwrap();
+        assert_eq!(reserved_b, vec![utxo(1)]);
+    }
+
+    #[test]
+    fn a_claim_cannot_take_another_claims_coin() {
+        let manager = BumpManager::new();
+        let claim_a = ClaimId([1u8; 32]);
+        let claim_b = ClaimId([2u8; 32]);
+        manager.reserve_available(claim_a, &[utxo(0)]).unwrap();
+        assert!(manager.reserve_available(claim_b, &[utxo(0)]).is_err());
+    }
+
+    #[test]
+    fn release_frees_coins_for_other_claims() {
+        let manager = BumpManager::new();
+        let claim_a = ClaimId([1u8; 32]);
+        let claim_b = ClaimId([2u8; 32]);
+        manager.reserve_available(claim_a, &[utxo(0)]).unwrap();
+        manager.release(claim_a);
+        assert!(manager.reserve_available(claim_b, &[utxo(0)]).is_ok());
+    }
+
+    #[test]
+    fn next_fee_rate_strictly_increases_on_repeat_bump() {
+        let manager = BumpManager::new();
+        let claim = ClaimId([1u8; 32]);
+        let first = manager.next_fee_rate(claim, 5);
+        let second = manager.next_fee_rate(claim, 5);
+        assert!(second > first);
+    }
+}
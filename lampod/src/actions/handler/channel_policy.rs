@@ -0,0 +1,228 @@
+//! Policy deciding whether an inbound channel open request is accepted.
+use std::sync::RwLock;
+
+use lampo_common::error;
+use lampo_common::ldk::routing::gossip::NodeId;
+
+/// Parse a hex-encoded compressed pubkey into a [`NodeId`].
+fn parse_node_id(hex_str: &str) -> error::Result<NodeId> {
+    if hex_str.len() != 66 {
+        error::bail!("expected a 33-byte hex-encoded node id, got {} chars", hex_str.len());
+    }
+    let mut bytes = [0u8; 33];
+    for (i, out) in bytes.iter_mut().enumerate() {
+        *out = u8::from_str_radix(&hex_str[i * 2..i * 2 + 2], 16)
+            .map_err(|err| error::anyhow!("invalid hex node id `{hex_str}`: {err}"))?;
+    }
+    NodeId::from_slice(&bytes).map_err(|err| error::anyhow!("invalid node id `{hex_str}`: {err:?}"))
+}
+
+/// Configurable policy applied to `Event::OpenChannelRequest`.
+///
+/// Defaults to rejecting everything, matching today's behaviour, until a
+/// caller configures it through the `configure_channel_policy` external
+/// command.
+pub struct ChannelAcceptancePolicy {
+    inner: RwLock<PolicyConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyConfig {
+    pub allow_list: Vec<NodeId>,
+    pub deny_list: Vec<NodeId>,
+    /// Whether to accept a peer that is on neither list at all. Defaults to
+    /// `false` so an unconfigured policy rejects every peer instead of
+    /// silently accepting everyone; a caller has to opt in explicitly to
+    /// accept unlisted peers.
+    pub accept_unlisted_peers: bool,
+    pub min_funding_satoshis: u64,
+    pub accept_zero_conf_for_allowed_peers: bool,
+    pub accept_anchors: bool,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            allow_list: Vec::new(),
+            deny_list: Vec::new(),
+            accept_unlisted_peers: false,
+            min_funding_satoshis: 0,
+            accept_zero_conf_for_allowed_peers: false,
+            accept_anchors: true,
+        }
+    }
+}
+
+/// Wire format accepted by the `configure_channel_policy` external command.
+/// Node ids are hex-encoded compressed pubkeys, same as everywhere else in
+/// the RPC surface.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PolicyConfigRequest {
+    #[serde(default)]
+    pub allow_list: Vec<String>,
+    #[serde(default)]
+    pub deny_list: Vec<String>,
+    #[serde(default)]
+    pub accept_unlisted_peers: bool,
+    #[serde(default)]
+    pub min_funding_satoshis: u64,
+    #[serde(default)]
+    pub accept_zero_conf_for_allowed_peers: bool,
+    #[serde(default = "default_accept_anchors")]
+    pub accept_anchors: bool,
+}
+
+fn default_accept_anchors() -> bool {
+    true
+}
+
+impl TryFrom<PolicyConfigRequest> for PolicyConfig {
+    type Error = error::Error;
+
+    fn try_from(request: PolicyConfigRequest) -> error::Result<Self> {
+        let parse_list = |list: Vec<String>| -> error::Result<Vec<NodeId>> {
+            list.iter().map(|node_id| parse_node_id(node_id)).collect()
+        };
+        Ok(Self {
+            allow_list: parse_list(request.allow_list)?,
+            deny_list: parse_list(request.deny_list)?,
+            accept_unlisted_peers: request.accept_unlisted_peers,
+            min_funding_satoshis: request.min_funding_satoshis,
+            accept_zero_conf_for_allowed_peers: request.accept_zero_conf_for_allowed_peers,
+            accept_anchors: request.accept_anchors,
+        })
+    }
+}
+
+/// Outcome of evaluating a request against the configured policy.
+pub enum PolicyDecision {
+    Accept { zero_conf: bool },
+    Reject { reason: String },
+}
+
+impl ChannelAcceptancePolicy {
+    pub fn new() -> Self {
+        Self {
+            inner: RwLock::new(PolicyConfig::default()),
+        }
+    }
+
+    pub fn configure(&self, config: PolicyConfig) {
+        *self.inner.write().unwrap() = config;
+    }
+
+    pub fn evaluate(
+        &self,
+        counterparty_node_id: &NodeId,
+        funding_satoshis: u64,
+        wants_anchors: bool,
+    ) -> PolicyDecision {
+        let config = self.inner.read().unwrap();
+        if config.deny_list.contains(counterparty_node_id) {
+            return PolicyDecision::Reject {
+                reason: "peer is on the deny list".to_owned(),
+            };
+        }
+        let is_allow_listed = config.allow_list.contains(counterparty_node_id);
+        if !is_allow_listed && !config.accept_unlisted_peers {
+            return PolicyDecision::Reject {
+                reason: "peer is not on the allow list".to_owned(),
+            };
+        }
+        if funding_satoshis < config.min_funding_satoshis {
+            return PolicyDecision::Reject {
+                reason: format!(
+                    "funding {funding_satoshis} sat is below the minimum {} sat",
+                    config.min_funding_satoshis
+                ),
+            };
+        }
+        if wants_anchors && !config.accept_anchors {
+            return PolicyDecision::Reject {
+                reason: "anchor output channels are not accepted".to_owned(),
+            };
+        }
+        let zero_conf = config.accept_zero_conf_for_allowed_peers && is_allow_listed;
+        PolicyDecision::Accept { zero_conf }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_id(byte: u8) -> NodeId {
+        let hex_str: String = std::iter::once("02".to_owned())
+            .chain(std::iter::repeat(format!("{byte:02x}")).take(32))
+            .collect();
+        parse_node_id(&hex_str).unwrap()
+    }
+
+    #[test]
+    fn rejects_peers_on_the_deny_list() {
+        let policy = ChannelAcceptancePolicy::new();
+        policy.configure(PolicyConfig {
+            deny_list: vec![node_id(1)],
+            ..PolicyConfig::default()
+        });
+        assert!(matches!(
+            policy.evaluate(&node_id(1), 1_000_000, false),
+            PolicyDecision::Reject { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_funding_below_minimum() {
+        let policy = ChannelAcceptancePolicy::new();
+        policy.configure(PolicyConfig {
+            accept_unlisted_peers: true,
+            min_funding_satoshis: 500_000,
+            ..PolicyConfig::default()
+        });
+        assert!(matches!(
+            policy.evaluate(&node_id(2), 100_000, false),
+            PolicyDecision::Reject { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_unlisted_peers_by_default() {
+        let policy = ChannelAcceptancePolicy::new();
+        assert!(matches!(
+            policy.evaluate(&node_id(9), 1_000_000, false),
+            PolicyDecision::Reject { .. }
+        ));
+    }
+
+    #[test]
+    fn accepts_zero_conf_only_for_allow_listed_peers() {
+        let policy = ChannelAcceptancePolicy::new();
+        policy.configure(PolicyConfig {
+            allow_list: vec![node_id(3)],
+            accept_zero_conf_for_allowed_peers: true,
+            ..PolicyConfig::default()
+        });
+        assert!(matches!(
+            policy.evaluate(&node_id(3), 1_000_000, false),
+            PolicyDecision::Accept { zero_conf: true }
+        ));
+        assert!(matches!(
+            policy.evaluate(&node_id(4), 1_000_000, false),
+            PolicyDecision::Reject { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_anchors_when_disabled() {
+        let policy = ChannelAcceptancePolicy::new();
+        policy.configure(PolicyConfig {
+            accept_unlisted_peers: true,
+            accept_anchors: false,
+            ..PolicyConfig::default()
+        });
+        assert!(matches!(
+            policy.evaluate(&node_id(5), 1_000_000, true),
+            PolicyDecision::Reject { .. }
+        ));
+    }
+}
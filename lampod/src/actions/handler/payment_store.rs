@@ -0,0 +1,311 @@
+//! Persistent storage for inbound and outbound payments.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use lampo_common::error;
+use lampo_common::ldk::ln::types::PaymentHash;
+use lampo_common::ldk::types::payment::{PaymentPreimage, PaymentSecret};
+
+/// Direction of a payment relative to this node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PaymentDirection {
+    Inbound,
+    Outbound,
+}
+
+/// Lifecycle state of a tracked payment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PaymentStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// A single record tracked by the [`PaymentStore`].
+#[derive(Debug, Clone)]
+pub struct PaymentDetails {
+    pub payment_hash: PaymentHash,
+    pub payment_preimage: Option<PaymentPreimage>,
+    pub payment_secret: Option<PaymentSecret>,
+    pub amount_msat: Option<u64>,
+    pub direction: PaymentDirection,
+    pub status: PaymentStatus,
+}
+
+/// RPC-facing view of a [`PaymentDetails`] record, returned by the
+/// `list_payments` external command.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PaymentDetailsResponse {
+    pub payment_hash: String,
+    pub payment_preimage: Option<String>,
+    pub amount_msat: Option<u64>,
+    pub direction: PaymentDirection,
+    pub status: PaymentStatus,
+}
+
+impl PaymentDetails {
+    pub fn as_response(&self) -> PaymentDetailsResponse {
+        PaymentDetailsResponse {
+            payment_hash: encode_hex(&self.payment_hash.0),
+            payment_preimage: self.payment_preimage.map(|p| encode_hex(&p.0)),
+            amount_msat: self.amount_msat,
+            direction: self.direction,
+            status: self.status,
+        }
+    }
+
+    pub fn new(
+        payment_hash: PaymentHash,
+        direction: PaymentDirection,
+        amount_msat: Option<u64>,
+    ) -> Self {
+        Self {
+            payment_hash,
+            payment_preimage: None,
+            payment_secret: None,
+            amount_msat,
+            direction,
+            status: PaymentStatus::Pending,
+        }
+    }
+}
+
+/// Wire format for a [`PaymentDetails`] record, serialized through the
+/// wallet/daemon's storage path. Binary LDK types are hex-encoded so the
+/// on-disk format doesn't depend on whether those types implement serde.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PersistedPayment {
+    payment_hash: String,
+    payment_preimage: Option<String>,
+    payment_secret: Option<String>,
+    amount_msat: Option<u64>,
+    direction: PaymentDirection,
+    status: PaymentStatus,
+}
+
+fn encode_hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(hex_str: &str) -> error::Result<[u8; 32]> {
+    if hex_str.len() != 64 {
+        error::bail!("expected a 32-byte hex string, got {} chars", hex_str.len());
+    }
+    let mut out = [0u8; 32];
+    for (i, chunk) in out.iter_mut().enumerate() {
+        *chunk = u8::from_str_radix(&hex_str[i * 2..i * 2 + 2], 16)
+            .map_err(|err| error::anyhow!("invalid hex in payment store: {err}"))?;
+    }
+    Ok(out)
+}
+
+impl From<&PaymentDetails> for PersistedPayment {
+    fn from(details: &PaymentDetails) -> Self {
+        Self {
+            payment_hash: encode_hex(&details.payment_hash.0),
+            payment_preimage: details.payment_preimage.map(|p| encode_hex(&p.0)),
+            payment_secret: details.payment_secret.map(|s| encode_hex(&s.0)),
+            amount_msat: details.amount_msat,
+            direction: details.direction,
+            status: details.status,
+        }
+    }
+}
+
+impl TryFrom<PersistedPayment> for PaymentDetails {
+    type Error = error::Error;
+
+    fn try_from(persisted: PersistedPayment) -> error::Result<Self> {
+        let payment_preimage = persisted
+            .payment_preimage
+            .as_deref()
+            .map(decode_hex)
+            .transpose()?
+            .map(PaymentPreimage);
+        let payment_secret = persisted
+            .payment_secret
+            .as_deref()
+            .map(decode_hex)
+            .transpose()?
+            .map(PaymentSecret);
+        Ok(Self {
+            payment_hash: PaymentHash(decode_hex(&persisted.payment_hash)?),
+            payment_preimage,
+            payment_secret,
+            amount_msat: persisted.amount_msat,
+            direction: persisted.direction,
+            status: persisted.status,
+        })
+    }
+}
+
+/// Durable, queryable store of payment history.
+///
+/// Mutations are kept in an in-memory map that mirrors a JSON file at
+/// `<data_dir>/payments.json`; every mutation rewrites that file so the
+/// history survives a restart, and [`PaymentStore::reload`] restores the
+/// in-memory view from it on startup.
+pub struct PaymentStore {
+    payments: Mutex<HashMap<PaymentHash, PaymentDetails>>,
+    storage_path: PathBuf,
+}
+
+impl PaymentStore {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            payments: Mutex::new(HashMap::new()),
+            storage_path: data_dir.join("payments.json"),
+        }
+    }
+
+    /// Reload the payment history that was persisted on a previous run.
+    pub fn reload(&self) -> error::Result<()> {
+        if !self.storage_path.exists() {
+            log::info!(target: "payment_store", "no payment history found at `{}`, starting empty", self.storage_path.display());
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.storage_path)
+            .map_err(|err| error::anyhow!("unable to read payment store `{}`: {err}", self.storage_path.display()))?;
+        let persisted: Vec<PersistedPayment> = lampo_common::json::from_str(&raw)
+            .map_err(|err| error::anyhow!("unable to parse payment store `{}`: {err}", self.storage_path.display()))?;
+        let mut payments = self.payments.lock().unwrap();
+        for entry in persisted {
+            let details = PaymentDetails::try_from(entry)?;
+            payments.insert(details.payment_hash, details);
+        }
+        log::info!(target: "payment_store", "reloaded {} payment(s) from `{}`", payments.len(), self.storage_path.display());
+        Ok(())
+    }
+
+    /// Insert a new record, or leave the existing one untouched.
+    pub fn upsert_pending(
+        &self,
+        payment_hash: PaymentHash,
+        direction: PaymentDirection,
+        amount_msat: Option<u64>,
+    ) {
+        let mut payments = self.payments.lock().unwrap();
+        payments
+            .entry(payment_hash)
+            .or_insert_with(|| PaymentDetails::new(payment_hash, direction, amount_msat));
+        self.persist(&payments);
+    }
+
+    /// Mark an inbound payment as succeeded, recording the resolved preimage/secret.
+    pub fn mark_claimed(
+        &self,
+        payment_hash: PaymentHash,
+        payment_preimage: Option<PaymentPreimage>,
+        payment_secret: Option<PaymentSecret>,
+    ) {
+        let mut payments = self.payments.lock().unwrap();
+        let record = payments
+            .entry(payment_hash)
+            .or_insert_with(|| PaymentDetails::new(payment_hash, PaymentDirection::Inbound, None));
+        record.status = PaymentStatus::Succeeded;
+        record.payment_preimage = payment_preimage;
+        record.payment_secret = payment_secret;
+        self.persist(&payments);
+    }
+
+    /// Mark the matching outbound payment as succeeded.
+    pub fn mark_sent(&self, payment_hash: PaymentHash, payment_preimage: Option<PaymentPreimage>) {
+        let mut payments = self.payments.lock().unwrap();
+        let record = payments
+            .entry(payment_hash)
+            .or_insert_with(|| PaymentDetails::new(payment_hash, PaymentDirection::Outbound, None));
+        record.status = PaymentStatus::Succeeded;
+        record.payment_preimage = payment_preimage;
+        self.persist(&payments);
+    }
+
+    /// Mark the matching outbound payment as failed.
+    pub fn mark_failed(&self, payment_hash: PaymentHash) {
+        let mut payments = self.payments.lock().unwrap();
+        let record = payments
+            .entry(payment_hash)
+            .or_insert_with(|| PaymentDetails::new(payment_hash, PaymentDirection::Outbound, None));
+        record.status = PaymentStatus::Failed;
+        self.persist(&payments);
+    }
+
+    /// Full payment history, exposed through the `list_payments` external command.
+    pub fn list(&self) -> Vec<PaymentDetails> {
+        self.payments.lock().unwrap().values().cloned().collect()
+    }
+
+    fn persist(&self, payments: &HashMap<PaymentHash, PaymentDetails>) {
+        let persisted: Vec<PersistedPayment> = payments.values().map(PersistedPayment::from).collect();
+        let json = match lampo_common::json::to_string_pretty(&persisted) {
+            Ok(json) => json,
+            Err(err) => {
+                log::warn!(target: "payment_store", "unable to serialize payment history: {err}");
+                return;
+            }
+        };
+        // Write to a temporary file first so a crash mid-write can't corrupt
+        // the history that's already on disk.
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        if let Err(err) = fs::write(&tmp_path, json) {
+            log::warn!(target: "payment_store", "unable to write `{}`: {err}", tmp_path.display());
+            return;
+        }
+        if let Err(err) = fs::rename(&tmp_path, &self.storage_path) {
+            log::warn!(target: "payment_store", "unable to persist payment history to `{}`: {err}", self.storage_path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_hash(byte: u8) -> PaymentHash {
+        PaymentHash([byte; 32])
+    }
+
+    #[test]
+    fn upsert_then_claim_transitions_pending_to_succeeded() {
+        let store = PaymentStore::new(std::env::temp_dir().join(format!("lampo-test-{}", uuid_like())));
+        let hash = sample_hash(1);
+        store.upsert_pending(hash, PaymentDirection::Inbound, Some(1_000));
+        assert_eq!(store.list().len(), 1);
+        assert_eq!(store.list()[0].status, PaymentStatus::Pending);
+
+        store.mark_claimed(hash, Some(PaymentPreimage([2u8; 32])), Some(PaymentSecret([3u8; 32])));
+        let record = store.list().into_iter().find(|p| p.payment_hash == hash).unwrap();
+        assert_eq!(record.status, PaymentStatus::Succeeded);
+        assert_eq!(record.payment_preimage, Some(PaymentPreimage([2u8; 32])));
+        let _ = fs::remove_file(store.storage_path);
+    }
+
+    #[test]
+    fn reload_restores_persisted_history() {
+        let data_dir = std::env::temp_dir().join(format!("lampo-test-{}", uuid_like()));
+        fs::create_dir_all(&data_dir).unwrap();
+        let hash = sample_hash(7);
+        {
+            let store = PaymentStore::new(data_dir.clone());
+            store.upsert_pending(hash, PaymentDirection::Outbound, Some(500));
+            store.mark_sent(hash, Some(PaymentPreimage([9u8; 32])));
+        }
+
+        let reloaded = PaymentStore::new(data_dir.clone());
+        reloaded.reload().unwrap();
+        let record = reloaded.list().into_iter().find(|p| p.payment_hash == hash).unwrap();
+        assert_eq!(record.status, PaymentStatus::Succeeded);
+        assert_eq!(record.direction, PaymentDirection::Outbound);
+
+        let _ = fs::remove_dir_all(data_dir);
+    }
+
+    fn uuid_like() -> u128 {
+        // Unique-enough suffix per test run so parallel tests don't share a
+        // scratch directory, without pulling in a random/time source.
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        (std::process::id() as u128) << 32 | count as u128
+    }
+}
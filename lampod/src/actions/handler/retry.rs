@@ -0,0 +1,156 @@
+//! Advisory budget tracking for outbound payments that fail along a path.
+//!
+//! Despite the module name, nothing here retains route hints or resends a
+//! payment: LDK's own `Retry` policy, set when the payment was first sent,
+//! owns all actual retry behaviour. This module only counts path failures
+//! and elapsed time per payment hash and reports when that is worth
+//! logging a warning about.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lampo_common::ldk::ln::types::PaymentHash;
+
+/// Advisory bounds on how many path failures and how much wall-clock time a
+/// payment is allowed to rack up before a warning is logged about it. This
+/// never drives a resend or gives up on a payment itself: LDK keeps retrying
+/// internally according to the `Retry` policy a payment was sent with,
+/// and only the terminal `PaymentFailed` event means the payment is
+/// actually done.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub total_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            total_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Wire format accepted by the `configure_retry_policy` external command.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RetryPolicyRequest {
+    pub max_attempts: u32,
+    pub total_timeout_secs: u64,
+}
+
+impl From<RetryPolicyRequest> for RetryPolicy {
+    fn from(request: RetryPolicyRequest) -> Self {
+        Self {
+            max_attempts: request.max_attempts,
+            total_timeout: Duration::from_secs(request.total_timeout_secs),
+        }
+    }
+}
+
+struct Attempt {
+    path_failures: u32,
+    first_seen: Instant,
+}
+
+/// Tracks path-failure occurrences per payment hash.
+///
+/// A single outbound MPP payment can emit many `PaymentPathFailed` events
+/// for one logical "round" (one per HTLC part), so this only ever reports
+/// whether a payment has exceeded its configured budget; it never decides
+/// that a payment is finished. That decision belongs solely to the
+/// `PaymentFailed`/`PaymentSent` events, which are terminal.
+pub struct RetryTracker {
+    policy: Mutex<RetryPolicy>,
+    attempts: Mutex<HashMap<PaymentHash, Attempt>>,
+}
+
+impl RetryTracker {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy: Mutex::new(policy),
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replace the policy applied to attempts recorded from now on.
+    /// Surfaced through the `configure_retry_policy` external command.
+    pub fn configure(&self, policy: RetryPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    /// Record a path failure and report whether the payment has exceeded
+    /// the configured attempt count or total timeout. Purely advisory: the
+    /// caller should keep waiting for a terminal event regardless, but can
+    /// use this to log or surface a warning once a payment is clearly
+    /// overrunning its budget.
+    pub fn record_path_failure(&self, payment_hash: PaymentHash) -> bool {
+        let policy = *self.policy.lock().unwrap();
+        let mut attempts = self.attempts.lock().unwrap();
+        let attempt = attempts.entry(payment_hash).or_insert_with(|| Attempt {
+            path_failures: 0,
+            first_seen: Instant::now(),
+        });
+        attempt.path_failures += 1;
+        attempt.path_failures > policy.max_attempts || attempt.first_seen.elapsed() > policy.total_timeout
+    }
+
+    pub fn clear(&self, payment_hash: &PaymentHash) {
+        self.attempts.lock().unwrap().remove(payment_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> PaymentHash {
+        PaymentHash([byte; 32])
+    }
+
+    #[test]
+    fn reports_over_budget_once_max_attempts_exceeded() {
+        let tracker = RetryTracker::new(RetryPolicy {
+            max_attempts: 2,
+            total_timeout: Duration::from_secs(3600),
+        });
+        assert!(!tracker.record_path_failure(hash(1)));
+        assert!(!tracker.record_path_failure(hash(1)));
+        assert!(tracker.record_path_failure(hash(1)));
+    }
+
+    #[test]
+    fn tracks_independent_payments_separately() {
+        let tracker = RetryTracker::new(RetryPolicy {
+            max_attempts: 1,
+            total_timeout: Duration::from_secs(3600),
+        });
+        assert!(!tracker.record_path_failure(hash(1)));
+        assert!(!tracker.record_path_failure(hash(2)));
+        assert!(tracker.record_path_failure(hash(1)));
+        assert!(tracker.record_path_failure(hash(2)));
+    }
+
+    #[test]
+    fn clear_resets_the_attempt_count() {
+        let tracker = RetryTracker::new(RetryPolicy {
+            max_attempts: 1,
+            total_timeout: Duration::from_secs(3600),
+        });
+        assert!(!tracker.record_path_failure(hash(1)));
+        assert!(tracker.record_path_failure(hash(1)));
+        tracker.clear(&hash(1));
+        assert!(!tracker.record_path_failure(hash(1)));
+    }
+
+    #[test]
+    fn reports_over_budget_once_total_timeout_elapses() {
+        let tracker = RetryTracker::new(RetryPolicy {
+            max_attempts: 1_000,
+            total_timeout: Duration::from_millis(50),
+        });
+        assert!(!tracker.record_path_failure(hash(1)));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(tracker.record_path_failure(hash(1)));
+    }
+}
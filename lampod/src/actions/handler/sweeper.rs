@@ -0,0 +1,225 @@
+//! Background sweeper that moves spendable outputs from closed channels
+//! back into the wallet.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use lampo_common::bitcoin::Txid;
+use lampo_common::error;
+use lampo_common::ldk::sign::SpendableOutputDescriptor;
+use lampo_common::ldk::util::ser::{Readable, Writeable};
+
+/// Tracks [`SpendableOutputDescriptor`]s handed back by LDK until the
+/// transaction that sweeps them into the wallet confirms.
+///
+/// Descriptors are persisted as soon as they are received so a restart
+/// between the `SpendableOutputs` event and confirmation does not lose the
+/// funds; [`OutputSweeper::reload`] restores that pending set on startup.
+/// Persistence uses LDK's own `Writeable`/`Readable` wire format for the
+/// descriptors (they don't implement serde), wrapped in a small JSON
+/// envelope for the rest of the bookkeeping.
+pub struct OutputSweeper {
+    pending: Mutex<HashMap<u64, PendingSweep>>,
+    next_id: AtomicU64,
+    storage_path: PathBuf,
+}
+
+#[derive(Clone)]
+struct PendingSweep {
+    descriptors: Vec<SpendableOutputDescriptor>,
+    sweep_txid: Option<Txid>,
+    last_fee_rate_sat_per_vb: Option<u32>,
+}
+
+/// Read-only view of a pending sweep, handed to callers that drive
+/// confirmation tracking and fee bumps (the chain/keys managers live
+/// outside this module).
+pub struct PendingSweepView {
+    pub id: u64,
+    pub descriptors: Vec<SpendableOutputDescriptor>,
+    pub sweep_txid: Option<Txid>,
+    pub last_fee_rate_sat_per_vb: Option<u32>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PersistedSweep {
+    id: u64,
+    descriptors_hex: Vec<String>,
+    sweep_txid: Option<String>,
+    last_fee_rate_sat_per_vb: Option<u32>,
+}
+
+impl OutputSweeper {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            storage_path: data_dir.join("pending_sweeps.json"),
+        }
+    }
+
+    /// Restore descriptors that were persisted before a previous shutdown.
+    pub fn reload(&self) -> error::Result<()> {
+        if !self.storage_path.exists() {
+            log::info!(target: "sweeper", "no pending sweeps found at `{}`", self.storage_path.display());
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.storage_path)
+            .map_err(|err| error::anyhow!("unable to read `{}`: {err}", self.storage_path.display()))?;
+        let persisted: Vec<PersistedSweep> = lampo_common::json::from_str(&raw)
+            .map_err(|err| error::anyhow!("unable to parse `{}`: {err}", self.storage_path.display()))?;
+        let mut pending = self.pending.lock().unwrap();
+        let mut max_id = 0;
+        for entry in persisted {
+            max_id = max_id.max(entry.id);
+            let descriptors = entry
+                .descriptors_hex
+                .iter()
+                .map(|hex_str| decode_descriptor(hex_str))
+                .collect::<error::Result<Vec<_>>>()?;
+            let sweep_txid = entry
+                .sweep_txid
+                .as_deref()
+                .map(|txid| txid.parse::<Txid>())
+                .transpose()
+                .map_err(|err| error::anyhow!("corrupted sweep txid in store: {err}"))?;
+            pending.insert(
+                entry.id,
+                PendingSweep {
+                    descriptors,
+                    sweep_txid,
+                    last_fee_rate_sat_per_vb: entry.last_fee_rate_sat_per_vb,
+                },
+            );
+        }
+        self.next_id.store(max_id + 1, Ordering::Relaxed);
+        log::info!(target: "sweeper", "reloaded {} pending sweep(s) from `{}`", pending.len(), self.storage_path.display());
+        Ok(())
+    }
+
+    /// Register a freshly received batch of descriptors, returning the id
+    /// used to track it until the sweep confirms.
+    pub fn track(&self, descriptors: Vec<SpendableOutputDescriptor>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut pending = self.pending.lock().unwrap();
+        pending.insert(
+            id,
+            PendingSweep {
+                descriptors,
+                sweep_txid: None,
+                last_fee_rate_sat_per_vb: None,
+            },
+        );
+        self.persist(&pending);
+        id
+    }
+
+    /// Record the txid and feerate of the transaction that was broadcast to
+    /// sweep a pending batch, so confirmation tracking and fee bumps can
+    /// find it.
+    pub fn record_broadcast(&self, id: u64, txid: Txid, fee_rate_sat_per_vb: u32) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(sweep) = pending.get_mut(&id) {
+            sweep.sweep_txid = Some(txid);
+            sweep.last_fee_rate_sat_per_vb = Some(fee_rate_sat_per_vb);
+        }
+        self.persist(&pending);
+    }
+
+    /// Drop a batch once its sweep transaction has confirmed.
+    pub fn mark_confirmed(&self, id: u64) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.remove(&id);
+        self.persist(&pending);
+    }
+
+    /// Snapshot of every batch still awaiting confirmation, used by the
+    /// handler to poll the chain manager and rebroadcast at a bumped fee.
+    pub fn snapshot(&self) -> Vec<PendingSweepView> {
+        self.pending
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, sweep)| PendingSweepView {
+                id: *id,
+                descriptors: sweep.descriptors.clone(),
+                sweep_txid: sweep.sweep_txid,
+                last_fee_rate_sat_per_vb: sweep.last_fee_rate_sat_per_vb,
+            })
+            .collect()
+    }
+
+    fn persist(&self, pending: &HashMap<u64, PendingSweep>) {
+        let persisted: Vec<PersistedSweep> = pending
+            .iter()
+            .map(|(id, sweep)| PersistedSweep {
+                id: *id,
+                descriptors_hex: sweep.descriptors.iter().map(encode_descriptor).collect(),
+                sweep_txid: sweep.sweep_txid.map(|txid| txid.to_string()),
+                last_fee_rate_sat_per_vb: sweep.last_fee_rate_sat_per_vb,
+            })
+            .collect();
+        let json = match lampo_common::json::to_string_pretty(&persisted) {
+            Ok(json) => json,
+            Err(err) => {
+                log::warn!(target: "sweeper", "unable to serialize pending sweeps: {err}");
+                return;
+            }
+        };
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        if let Err(err) = fs::write(&tmp_path, json) {
+            log::warn!(target: "sweeper", "unable to write `{}`: {err}", tmp_path.display());
+            return;
+        }
+        if let Err(err) = fs::rename(&tmp_path, &self.storage_path) {
+            log::warn!(target: "sweeper", "unable to persist pending sweeps to `{}`: {err}", self.storage_path.display());
+        }
+    }
+}
+
+fn encode_descriptor(descriptor: &SpendableOutputDescriptor) -> String {
+    descriptor.encode().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_descriptor(hex_str: &str) -> error::Result<SpendableOutputDescriptor> {
+    if hex_str.len() % 2 != 0 {
+        error::bail!("odd-length hex descriptor in sweeper store");
+    }
+    let bytes = (0..hex_str.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex_str[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|err| error::anyhow!("invalid hex in sweeper store: {err}"))?;
+    SpendableOutputDescriptor::read(&mut &bytes[..])
+        .map_err(|err| error::anyhow!("corrupted descriptor in sweeper store: {err:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_broadcast_sets_txid_and_feerate() {
+        let dir = std::env::temp_dir().join(format!("lampo-sweeper-test-{}", std::process::id()));
+        let sweeper = OutputSweeper::new(dir.clone());
+        let id = sweeper.track(vec![]);
+        let txid = Txid::all_zeros();
+        sweeper.record_broadcast(id, txid, 5);
+        let view = sweeper.snapshot().into_iter().find(|v| v.id == id).unwrap();
+        assert_eq!(view.sweep_txid, Some(txid));
+        assert_eq!(view.last_fee_rate_sat_per_vb, Some(5));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn mark_confirmed_drops_the_batch() {
+        let dir = std::env::temp_dir().join(format!("lampo-sweeper-test-{}", std::process::id() + 1));
+        let sweeper = OutputSweeper::new(dir.clone());
+        let id = sweeper.track(vec![]);
+        sweeper.mark_confirmed(id);
+        assert!(sweeper.snapshot().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}